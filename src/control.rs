@@ -1,37 +1,132 @@
 use crate::cpu_event::{CpuEvent, CpuMonitor, SpinLooper};
+use crate::executor::{CoreClass, CoreExecutor};
 use std::error::Error;
 use std::rc::Rc;
+#[cfg(test)]
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::mpsc::{self, Receiver};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+/// How long `PCoreState` waits without a performance-core event before
+/// falling back to `ECoreState`, absent an explicit override.
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Upper bound on how long `PCoreState` blocks on `recv_timeout` between idle
+/// checks, so a long `idle_timeout` doesn't also mean a long, coarse-grained
+/// wait before the next check against [`Clock::now`].
+const MAX_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Abstracts "what time is it" so idle-timeout convergence can be driven by a
+/// fake, manually-advanced clock in tests instead of real wall-clock time.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+/// The real clock, used outside of tests.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only moves forward when explicitly told to via `advance`, so
+/// idle-timeout assertions in tests depend on logical ticks rather than on
+/// real thread-scheduling timing. Test-only: nothing outside `#[cfg(test)]`
+/// code (including the [`crate::simulation`] harness) ever constructs one.
+#[cfg(test)]
+pub struct FakeClock {
+    base: Instant,
+    elapsed_nanos: AtomicU64,
+}
+
+#[cfg(test)]
+impl FakeClock {
+    pub fn new() -> Self {
+        FakeClock {
+            base: Instant::now(),
+            elapsed_nanos: AtomicU64::new(0),
+        }
+    }
+
+    /// Moves the clock forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        self.elapsed_nanos
+            .fetch_add(duration.as_nanos() as u64, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+impl Default for FakeClock {
+    fn default() -> Self {
+        FakeClock::new()
+    }
+}
+
+#[cfg(test)]
+impl Clock for FakeClock {
+    fn now(&self) -> Instant {
+        self.base + Duration::from_nanos(self.elapsed_nanos.load(Ordering::SeqCst))
+    }
+}
+
 // Define a trait for core states
 trait CoreState {
     fn handle(&self, controller: &mut CoreStateController) -> Result<(), Box<dyn Error>>;
 }
 
+/// Abstracts `SpinLooper`'s start/stop so tests can substitute a counting
+/// fake instead of spinning real threads pinned to real cores.
+pub trait SpinControl: Send {
+    fn start(&mut self);
+    fn stop_and_join(&mut self);
+}
+
+impl SpinControl for SpinLooper {
+    fn start(&mut self) {
+        SpinLooper::start(self)
+    }
+
+    fn stop_and_join(&mut self) {
+        SpinLooper::stop_and_join(self)
+    }
+}
+
 // Define the state controller
 pub struct CoreStateController {
     receiver: Receiver<CpuEvent>,
     efficiency_monitor: Arc<CpuMonitor>,
     performance_monitor: Arc<CpuMonitor>,
-    spin_looper: SpinLooper,
+    spin_looper: Box<dyn SpinControl>,
     current_state: Rc<Box<dyn CoreState>>,
     last_event_time: Instant,
+    idle_timeout: Duration,
+    clock: Arc<dyn Clock>,
+    /// The work-stealing pool this controller's state transitions rebalance,
+    /// if one was attached. Absent in tests that drive the state machine
+    /// directly against a `CountingSpinControl`.
+    executor: Option<Arc<CoreExecutor>>,
 }
 
 impl CoreStateController {
+    /// Builds a controller wired to real hardware: starts `CpuMonitor`s and a
+    /// `SpinLooper` over the given core split, and attaches a `CoreExecutor`
+    /// pinned to the same split so state transitions can rebalance it.
     pub fn new(e_core_ids: Vec<usize>, rest_of_cores: Vec<usize>) -> Self {
         // Create monitors
         let efficiency_monitor = Arc::new(CpuMonitor::new(
             e_core_ids.clone(),
             CpuEvent::EfficiencyCoreMonitor(Vec::new()),
             true,
+            Duration::from_secs(1),
         ));
         let performance_monitor = Arc::new(CpuMonitor::new(
-            rest_of_cores,
+            rest_of_cores.clone(),
             CpuEvent::PerformanceCoreMonitor(Vec::new()),
             false,
+            Duration::from_secs(1),
         ));
         // Start the monitors
         let (sender, receiver) = mpsc::channel();
@@ -39,30 +134,94 @@ impl CoreStateController {
         CpuMonitor::start(performance_monitor.clone(), sender.clone());
 
         // Create and start the SpinLooper
-        let spin_looper = SpinLooper::new(e_core_ids);
+        let spin_looper = SpinLooper::new(e_core_ids.clone());
+        let executor = Arc::new(CoreExecutor::new(rest_of_cores, e_core_ids));
+        let mut controller = Self::with_parts(
+            receiver,
+            efficiency_monitor,
+            performance_monitor,
+            Box::new(spin_looper),
+            DEFAULT_IDLE_TIMEOUT,
+        );
+        controller.attach_executor(executor);
+        controller
+    }
+
+    /// Attaches the [`CoreExecutor`] this controller's state transitions
+    /// should rebalance work onto. Not set by [`CoreStateController::with_parts`]
+    /// or [`CoreStateController::with_parts_and_clock`], which tests use to
+    /// drive the state machine against a fake `SpinControl` with no executor
+    /// to speak of.
+    pub fn attach_executor(&mut self, executor: Arc<CoreExecutor>) {
+        self.executor = Some(executor);
+    }
+
+    /// Builds a controller from its parts directly, bypassing hardware
+    /// monitors and the real `SpinLooper`, using the real system clock.
+    pub fn with_parts(
+        receiver: Receiver<CpuEvent>,
+        efficiency_monitor: Arc<CpuMonitor>,
+        performance_monitor: Arc<CpuMonitor>,
+        spin_looper: Box<dyn SpinControl>,
+        idle_timeout: Duration,
+    ) -> Self {
+        Self::with_parts_and_clock(
+            receiver,
+            efficiency_monitor,
+            performance_monitor,
+            spin_looper,
+            idle_timeout,
+            Arc::new(SystemClock),
+        )
+    }
+
+    /// Builds a controller from its parts and an explicit [`Clock`]. Used to
+    /// drive the state machine from a `SimulatedMonitor` against a
+    /// `FakeClock` in tests, so idle-timeout convergence doesn't depend on
+    /// real thread-scheduling timing.
+    pub fn with_parts_and_clock(
+        receiver: Receiver<CpuEvent>,
+        efficiency_monitor: Arc<CpuMonitor>,
+        performance_monitor: Arc<CpuMonitor>,
+        spin_looper: Box<dyn SpinControl>,
+        idle_timeout: Duration,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        let last_event_time = clock.now();
         CoreStateController {
             receiver,
             efficiency_monitor,
             performance_monitor,
             spin_looper,
             current_state: Rc::new(Box::new(ECoreState)),
-            last_event_time: Instant::now(),
+            last_event_time,
+            idle_timeout,
+            clock,
+            executor: None,
         }
     }
 
     pub fn run(&mut self) -> Result<(), Box<dyn Error>> {
         loop {
-            let current_state = &self.current_state.clone();
-            current_state.handle(self)?;
+            self.run_once()?;
         }
     }
 
+    /// Runs a single step of the state machine. Exposed so tests can
+    /// interleave it with synthetic ticks from a single thread instead of
+    /// racing a real `run()` loop against a simulation thread.
+    fn run_once(&mut self) -> Result<(), Box<dyn Error>> {
+        let current_state = self.current_state.clone();
+        current_state.handle(self)
+    }
+
     fn switch_to_ecore_state(&mut self) {
         println!("Switching to ECoreState");
         self.performance_monitor.pause();
         self.efficiency_monitor.resume();
         self.spin_looper.stop_and_join();
         self.current_state = Rc::new(Box::new(ECoreState));
+        self.rebalance_executor(CoreClass::Efficiency);
     }
 
     fn switch_to_pcore_state(&mut self) {
@@ -71,6 +230,24 @@ impl CoreStateController {
         self.performance_monitor.resume();
         self.spin_looper.start();
         self.current_state = Rc::new(Box::new(PCoreState));
+        // Otherwise the idle-timeout check in `PCoreState::handle` measures
+        // elapsed time since the last real event seen in *any* state (or
+        // since construction), so a long dwell in `ECoreState` before this
+        // transition would make the very next poll in `PCoreState` look
+        // already idle and fall straight back out.
+        self.last_event_time = self.clock.now();
+        self.rebalance_executor(CoreClass::Performance);
+    }
+
+    /// Nudges the attached `CoreExecutor` towards the class of core this
+    /// controller just switched into, by submitting a no-op task tagged with
+    /// that class. Idle workers of the matching class wake up and any
+    /// already-queued same-class work gets a chance to run before the next
+    /// cross-class steal. A no-op if no executor was attached.
+    fn rebalance_executor(&self, class: CoreClass) {
+        if let Some(executor) = &self.executor {
+            executor.spawn(class, || {});
+        }
     }
 }
 
@@ -79,15 +256,23 @@ struct ECoreState;
 
 impl CoreState for ECoreState {
     fn handle(&self, controller: &mut CoreStateController) -> Result<(), Box<dyn Error>> {
-        match controller.receiver.recv() {
-            // Wait indefinitely for the event
+        // Polled rather than a plain `recv()`, so a caller driving the state
+        // machine one step at a time (e.g. a test interleaving synthetic
+        // ticks) always gets control back, even while no event is pending.
+        let poll_interval = controller.idle_timeout.min(MAX_POLL_INTERVAL);
+        match controller.receiver.recv_timeout(poll_interval) {
             Ok(CpuEvent::EfficiencyCoreMonitor(consumed_cores)) => {
                 println!("Efficiency cores fully consumed: {:?}", consumed_cores);
                 controller.switch_to_pcore_state();
             }
+            Ok(CpuEvent::MonitorError(e)) => {
+                eprintln!("CPU monitor error: {}", e);
+            }
             Ok(_) => {
                 // Ignore other events in this state
             }
+            // ECoreState has no idle timeout of its own; just poll again.
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
             Err(e) => {
                 return Err(Box::new(e));
             }
@@ -101,10 +286,15 @@ struct PCoreState;
 
 impl CoreState for PCoreState {
     fn handle(&self, controller: &mut CoreStateController) -> Result<(), Box<dyn Error>> {
-        match controller.receiver.recv_timeout(Duration::from_secs(10)) {
+        // Poll at a bounded granularity rather than blocking for the whole
+        // `idle_timeout`, so the idle check below always runs against the
+        // controller's `Clock` shortly after time actually passes there,
+        // regardless of how long `idle_timeout` itself is.
+        let poll_interval = controller.idle_timeout.min(MAX_POLL_INTERVAL);
+        match controller.receiver.recv_timeout(poll_interval) {
             Ok(CpuEvent::PerformanceCoreMonitor(consumed_cores)) => {
                 println!("Performance cores fully consumed: {:?}", consumed_cores);
-                controller.last_event_time = Instant::now();
+                controller.last_event_time = controller.clock.now();
             }
             Ok(CpuEvent::EfficiencyCoreMonitor(consumed_cores)) => {
                 println!(
@@ -112,9 +302,12 @@ impl CoreState for PCoreState {
                     consumed_cores
                 );
             }
+            Ok(CpuEvent::MonitorError(e)) => {
+                eprintln!("CPU monitor error: {}", e);
+            }
             Err(mpsc::RecvTimeoutError::Timeout) => {
-                let elapsed = controller.last_event_time.elapsed();
-                if elapsed >= Duration::from_secs(10) {
+                let elapsed = controller.clock.now().duration_since(controller.last_event_time);
+                if elapsed >= controller.idle_timeout {
                     controller.switch_to_ecore_state();
                 }
             }
@@ -125,3 +318,159 @@ impl CoreState for PCoreState {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu_event::MockSource;
+    use crate::simulation::{SimulatedMonitor, SimulationConfig};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// A `SpinControl` fake that just counts calls instead of spinning real threads.
+    struct CountingSpinControl {
+        starts: Arc<AtomicUsize>,
+        stops: Arc<AtomicUsize>,
+    }
+
+    impl SpinControl for CountingSpinControl {
+        fn start(&mut self) {
+            self.starts.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn stop_and_join(&mut self) {
+            self.stops.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn converges_through_simulated_events() {
+        let efficiency_monitor = Arc::new(CpuMonitor::with_source(
+            vec![0],
+            CpuEvent::EfficiencyCoreMonitor(Vec::new()),
+            true,
+            Duration::from_millis(1),
+            Box::new(MockSource::new()),
+        ));
+        let performance_monitor = Arc::new(CpuMonitor::with_source(
+            vec![0],
+            CpuEvent::PerformanceCoreMonitor(Vec::new()),
+            false,
+            Duration::from_millis(1),
+            Box::new(MockSource::new()),
+        ));
+
+        let starts = Arc::new(AtomicUsize::new(0));
+        let stops = Arc::new(AtomicUsize::new(0));
+        let spin_looper = CountingSpinControl {
+            starts: starts.clone(),
+            stops: stops.clone(),
+        };
+
+        let idle_timeout = Duration::from_millis(10);
+        let (sender, receiver) = mpsc::channel();
+        let clock = Arc::new(FakeClock::new());
+        let mut controller = CoreStateController::with_parts_and_clock(
+            receiver,
+            efficiency_monitor,
+            performance_monitor,
+            Box::new(spin_looper),
+            idle_timeout,
+            clock.clone(),
+        );
+
+        // Drive the simulation and the controller from this one thread, in
+        // lockstep: each tick advances `clock` and then lets the controller
+        // process up to one step against it, before the next tick's events
+        // are generated. That keeps the idle-timeout convergence below
+        // entirely deterministic instead of depending on whether a real
+        // background thread's sleeps and this thread's `recv_timeout` calls
+        // happen to race out in the same order every run.
+        SimulatedMonitor::run_synchronized(
+            SimulationConfig {
+                e_core_consumption_probability: 0.15,
+                p_core_consumption_probability: 0.05,
+                steps: 50,
+                seed: 42,
+                tick_interval: Duration::from_millis(2),
+            },
+            sender,
+            &clock,
+            || {
+                let _ = controller.run_once();
+            },
+        );
+
+        // The simulation dropped its sender once it finished, which unblocks
+        // the next `recv_timeout` call with a disconnect error.
+        let result = controller.run_once();
+        assert!(result.is_err());
+
+        // Over 50 ticks, at least one E-core event must fire, moving the
+        // controller out of ECoreState; and with low per-tick probabilities
+        // against a 10ms idle timeout, a quiet enough stretch must occur to
+        // fall back to ECoreState at least once too.
+        assert!(starts.load(Ordering::SeqCst) >= 1);
+        assert!(stops.load(Ordering::SeqCst) >= 1);
+    }
+
+    #[test]
+    fn pcore_state_survives_the_first_poll_after_a_long_ecore_dwell() {
+        let efficiency_monitor = Arc::new(CpuMonitor::with_source(
+            vec![0],
+            CpuEvent::EfficiencyCoreMonitor(Vec::new()),
+            true,
+            Duration::from_millis(1),
+            Box::new(MockSource::new()),
+        ));
+        let performance_monitor = Arc::new(CpuMonitor::with_source(
+            vec![0],
+            CpuEvent::PerformanceCoreMonitor(Vec::new()),
+            false,
+            Duration::from_millis(1),
+            Box::new(MockSource::new()),
+        ));
+
+        let starts = Arc::new(AtomicUsize::new(0));
+        let stops = Arc::new(AtomicUsize::new(0));
+        let spin_looper = CountingSpinControl {
+            starts: starts.clone(),
+            stops: stops.clone(),
+        };
+
+        let idle_timeout = Duration::from_millis(500);
+        let (sender, receiver) = mpsc::channel();
+        let clock = Arc::new(FakeClock::new());
+        let mut controller = CoreStateController::with_parts_and_clock(
+            receiver,
+            efficiency_monitor,
+            performance_monitor,
+            Box::new(spin_looper),
+            idle_timeout,
+            clock.clone(),
+        );
+
+        // Dwell in ECoreState past the idle timeout before the first real
+        // event, so a controller that forgets to refresh `last_event_time`
+        // on entry to `PCoreState` would see this stale elapsed time on its
+        // very next poll there.
+        clock.advance(idle_timeout);
+        sender
+            .send(CpuEvent::EfficiencyCoreMonitor(vec![0]))
+            .unwrap();
+        controller.run_once().unwrap();
+        assert_eq!(starts.load(Ordering::SeqCst), 1, "switching to PCoreState should start the spin looper");
+        assert_eq!(stops.load(Ordering::SeqCst), 0);
+
+        // One more poll with no new event and no further clock advance: a
+        // controller that correctly resets `last_event_time` on the
+        // transition should stay in PCoreState here; one that measures
+        // idleness from the stale `last_event_time` would immediately fall
+        // back to ECoreState instead of waiting out the idle timeout.
+        controller.run_once().unwrap();
+        assert_eq!(
+            stops.load(Ordering::SeqCst),
+            0,
+            "PCoreState should not revert to ECoreState on the very next poll after just transitioning into it"
+        );
+    }
+}
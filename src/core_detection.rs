@@ -9,6 +9,7 @@ use windows::{
     },
 };
 
+#[cfg(windows)]
 pub fn identify_e_cores() -> windows::core::Result<Vec<usize>> {
     let mut e_cores = Vec::new();
     let mut buffer_size: u32 = 0;
@@ -82,7 +83,194 @@ pub fn identify_e_cores() -> windows::core::Result<Vec<usize>> {
     Ok(e_cores)
 }
 
-#[cfg(not(windows))]
+/// A set of CPUs whose `cpu_capacity` values lie within the merge tolerance
+/// of one another, used to rank the heterogeneous clusters on an
+/// ARM/Intel-hybrid system. `capacity` is the lowest capacity in the set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoreCluster {
+    pub capacity: u32,
+    pub cpus: Vec<usize>,
+}
+
+/// How close two capacities must be, as a percentage of the larger value, to
+/// be folded into the same cluster. Real hybrid hardware routinely reports
+/// slightly different capacities within what is physically one cluster
+/// (rounding, per-core binning), so exact-equality grouping fragments it.
+#[cfg(target_os = "linux")]
+const CAPACITY_MERGE_TOLERANCE_PERCENT: u32 = 5;
+
+#[cfg(target_os = "linux")]
+fn capacities_are_adjacent(a: u32, b: u32) -> bool {
+    let threshold = a.max(b) * CAPACITY_MERGE_TOLERANCE_PERCENT / 100;
+    a.abs_diff(b) <= threshold
+}
+
+/// Reads `/sys/devices/system/cpu/cpuN/cpu_capacity` (falling back to
+/// `cpufreq/cpuinfo_max_freq` where capacity isn't exposed) for every online
+/// CPU and groups them into clusters, sorted ascending by capacity so the
+/// first entry is the efficiency set. A homogeneous system reports a single
+/// cluster.
+#[cfg(target_os = "linux")]
+pub fn identify_core_clusters() -> std::io::Result<Vec<CoreCluster>> {
+    clusters_from_cpu_dir(std::path::Path::new("/sys/devices/system/cpu"))
+}
+
+/// The guts of [`identify_core_clusters`], parameterized over the sysfs root
+/// so tests can point it at a fake directory tree instead of the real one.
+#[cfg(target_os = "linux")]
+fn clusters_from_cpu_dir(cpu_dir: &std::path::Path) -> std::io::Result<Vec<CoreCluster>> {
+    use std::collections::BTreeMap;
+    use std::fs;
+
+    let mut by_capacity: BTreeMap<u32, Vec<usize>> = BTreeMap::new();
+
+    for entry in fs::read_dir(cpu_dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        let Some(id_str) = name.strip_prefix("cpu") else {
+            continue;
+        };
+        let Ok(cpu_id) = id_str.parse::<usize>() else {
+            continue;
+        };
+
+        let cpu_path = entry.path();
+        if !cpu_is_online(&cpu_path) {
+            continue;
+        }
+
+        if let Some(capacity) = read_cpu_capacity(&cpu_path) {
+            by_capacity.entry(capacity).or_default().push(cpu_id);
+        }
+    }
+
+    // by_capacity is already sorted ascending by capacity (BTreeMap), so
+    // adjacent entries within tolerance can be folded into the running
+    // cluster as we go, rather than comparing every pair.
+    let mut clusters: Vec<CoreCluster> = Vec::new();
+    for (capacity, mut cpus) in by_capacity {
+        cpus.sort_unstable();
+        match clusters.last_mut() {
+            Some(last) if capacities_are_adjacent(last.capacity, capacity) => {
+                last.cpus.extend(cpus);
+                last.cpus.sort_unstable();
+            }
+            _ => clusters.push(CoreCluster { capacity, cpus }),
+        }
+    }
+
+    Ok(clusters)
+}
+
+// `cpu0` can never be offlined and has no `online` file, so its absence means online.
+#[cfg(target_os = "linux")]
+fn cpu_is_online(cpu_path: &std::path::Path) -> bool {
+    match std::fs::read_to_string(cpu_path.join("online")) {
+        Ok(contents) => contents.trim() == "1",
+        Err(_) => true,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn read_cpu_capacity(cpu_path: &std::path::Path) -> Option<u32> {
+    if let Some(capacity) = std::fs::read_to_string(cpu_path.join("cpu_capacity"))
+        .ok()
+        .and_then(|contents| contents.trim().parse::<u32>().ok())
+    {
+        return Some(capacity);
+    }
+    // cpu_capacity isn't populated on every kernel; the max scaling frequency
+    // is the closest proxy for relative core performance.
+    std::fs::read_to_string(cpu_path.join("cpufreq/cpuinfo_max_freq"))
+        .ok()
+        .and_then(|contents| contents.trim().parse::<u32>().ok())
+}
+
+/// Returns the lowest-capacity cluster (the efficiency cores) for callers
+/// that only need the binary E-core/P-core split.
+#[cfg(target_os = "linux")]
+pub fn identify_e_cores() -> std::io::Result<Vec<usize>> {
+    let clusters = identify_core_clusters()?;
+    Ok(clusters.into_iter().next().map(|c| c.cpus).unwrap_or_default())
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
 pub fn identify_e_cores() -> Result<Vec<usize>, String> {
-    Err("identify_e_cores is not supported on non-Windows platforms.".to_string())
+    Err("identify_e_cores is not supported on this platform.".to_string())
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    /// Builds a fresh, uniquely-named fake sysfs CPU directory under the
+    /// system temp dir, so tests don't touch the real `/sys`.
+    fn fake_cpu_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "core_detection_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_cpu(base: &Path, id: usize, capacity: Option<u32>, online: Option<bool>) {
+        let cpu_dir = base.join(format!("cpu{}", id));
+        fs::create_dir_all(&cpu_dir).unwrap();
+        if let Some(capacity) = capacity {
+            fs::write(cpu_dir.join("cpu_capacity"), capacity.to_string()).unwrap();
+        }
+        if let Some(online) = online {
+            fs::write(cpu_dir.join("online"), if online { "1" } else { "0" }).unwrap();
+        }
+    }
+
+    #[test]
+    fn merges_adjacent_capacities_within_tolerance() {
+        let dir = fake_cpu_dir("merge");
+        write_cpu(&dir, 0, Some(100), None);
+        write_cpu(&dir, 1, Some(102), None);
+        write_cpu(&dir, 2, Some(1000), None);
+        write_cpu(&dir, 3, Some(1024), None);
+
+        let clusters = clusters_from_cpu_dir(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0].capacity, 100);
+        assert_eq!(clusters[0].cpus, vec![0, 1]);
+        assert_eq!(clusters[1].cpus, vec![2, 3]);
+    }
+
+    #[test]
+    fn skips_offline_cpus() {
+        let dir = fake_cpu_dir("offline");
+        write_cpu(&dir, 0, Some(100), Some(true));
+        write_cpu(&dir, 1, Some(100), Some(false));
+
+        let clusters = clusters_from_cpu_dir(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].cpus, vec![0]);
+    }
+
+    #[test]
+    fn homogeneous_system_returns_single_cluster() {
+        let dir = fake_cpu_dir("homogeneous");
+        for id in 0..4 {
+            write_cpu(&dir, id, Some(1024), None);
+        }
+
+        let clusters = clusters_from_cpu_dir(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].cpus, vec![0, 1, 2, 3]);
+    }
 }
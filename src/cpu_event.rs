@@ -4,22 +4,296 @@ use std::sync::{
     mpsc, Arc, Condvar, Mutex,
 };
 use std::thread::{self, JoinHandle};
+use std::time::Duration;
 
 #[cfg(windows)]
 use windows::{
     core::PCWSTR,
     Win32::Foundation::ERROR_SUCCESS,
     Win32::System::Performance::{
-        PdhAddCounterW, PdhCollectQueryData, PdhGetFormattedCounterValue, PdhOpenQueryW,
-        PDH_CALC_NEGATIVE_DENOMINATOR, PDH_CALC_NEGATIVE_VALUE, PDH_CSTATUS_NEW_DATA,
-        PDH_CSTATUS_VALID_DATA, PDH_FMT_COUNTERVALUE, PDH_FMT_DOUBLE, PDH_INVALID_ARGUMENT,
-        PDH_INVALID_DATA,
+        PdhAddCounterW, PdhCloseQuery, PdhCollectQueryData, PdhGetFormattedCounterValue,
+        PdhOpenQueryW, PDH_CALC_NEGATIVE_DENOMINATOR, PDH_CALC_NEGATIVE_VALUE,
+        PDH_CSTATUS_NEW_DATA, PDH_CSTATUS_VALID_DATA, PDH_FMT_COUNTERVALUE, PDH_FMT_DOUBLE,
     },
 };
 // Define an enum for the events we are interested in
 pub enum CpuEvent {
     EfficiencyCoreMonitor(Vec<usize>),
     PerformanceCoreMonitor(Vec<usize>),
+    /// Sent when the underlying `CounterSource` fails to open, add a counter,
+    /// or collect a sample, instead of panicking the monitor thread.
+    MonitorError(String),
+}
+
+/// Abstracts the per-core utilization sampling a `CpuMonitor` drives, so the
+/// monitor can be exercised deterministically with a `MockSource` in tests
+/// and only talks to PDH through `PdhCounterSource` on Windows.
+pub trait CounterSource: Send {
+    /// Performs any one-time setup needed before sampling (e.g. opening a PDH query).
+    fn open(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Registers `core_index` so future `collect`/`value` calls cover it.
+    fn add_core(&mut self, core_index: usize) -> Result<(), String>;
+
+    /// Triggers a new sampling pass.
+    fn collect(&mut self) -> Result<(), String>;
+
+    /// Returns the most recently collected utilization percentage for `core`, if valid.
+    fn value(&self, core: usize) -> Option<f64>;
+}
+
+/// Reads per-core `% Processor Utility` via PDH. Closes its query handle on drop.
+#[cfg(windows)]
+#[derive(Default)]
+pub struct PdhCounterSource {
+    query: isize,
+    counters: Vec<(usize, isize)>,
+}
+
+#[cfg(windows)]
+impl PdhCounterSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(windows)]
+impl CounterSource for PdhCounterSource {
+    fn open(&mut self) -> Result<(), String> {
+        let status = unsafe { PdhOpenQueryW(PCWSTR::null(), 0, &mut self.query) };
+        if status != ERROR_SUCCESS.0 {
+            return Err(format!("PdhOpenQueryW failed with error: {}", status));
+        }
+        Ok(())
+    }
+
+    fn add_core(&mut self, core_index: usize) -> Result<(), String> {
+        let counter_path = format!(
+            r"\Processor Information(0,{})\% Processor Utility",
+            core_index
+        );
+        let wide_counter_path = widestring::U16CString::from_str(&counter_path)
+            .map_err(|e| format!("invalid counter path for core {}: {}", core_index, e))?;
+        let mut counter_handle: isize = 0;
+        let status = unsafe {
+            PdhAddCounterW(
+                self.query,
+                PCWSTR(wide_counter_path.as_ptr()),
+                0,
+                &mut counter_handle,
+            )
+        };
+        if status != ERROR_SUCCESS.0 {
+            return Err(format!("PdhAddCounterW failed with error: {}", status));
+        }
+        self.counters.push((core_index, counter_handle));
+        Ok(())
+    }
+
+    fn collect(&mut self) -> Result<(), String> {
+        let status = unsafe { PdhCollectQueryData(self.query) };
+        if status != ERROR_SUCCESS.0 {
+            return Err(format!("PdhCollectQueryData failed with error: {:x}", status));
+        }
+        Ok(())
+    }
+
+    fn value(&self, core: usize) -> Option<f64> {
+        let (_, handle) = self.counters.iter().find(|(c, _)| *c == core)?;
+        let mut counter_value = PDH_FMT_COUNTERVALUE::default();
+        let status = unsafe {
+            PdhGetFormattedCounterValue(
+                *handle,
+                PDH_FMT_DOUBLE,
+                Some(std::ptr::null_mut()),
+                &mut counter_value,
+            )
+        };
+        if status == PDH_CALC_NEGATIVE_VALUE || status == PDH_CALC_NEGATIVE_DENOMINATOR {
+            return None;
+        }
+        if status != ERROR_SUCCESS.0 {
+            return None;
+        }
+        if counter_value.CStatus == PDH_CSTATUS_VALID_DATA
+            || counter_value.CStatus == PDH_CSTATUS_NEW_DATA
+        {
+            Some(unsafe { counter_value.Anonymous.doubleValue })
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(windows)]
+impl Drop for PdhCounterSource {
+    fn drop(&mut self) {
+        if self.query != 0 {
+            unsafe {
+                let _ = PdhCloseQuery(self.query);
+            }
+        }
+    }
+}
+
+/// An in-memory `CounterSource`: utilization values are set directly and
+/// `collect()` is a no-op, so monitor behavior can be driven deterministically
+/// in tests. Also the production `default_counter_source()` fallback on
+/// platforms that are neither Windows nor Linux, where no real source exists
+/// yet — hence not purely `#[cfg(test)]` itself, unlike `set_value` below.
+#[cfg(any(test, not(any(windows, target_os = "linux"))))]
+#[derive(Clone, Default)]
+pub struct MockSource {
+    values: Arc<Mutex<std::collections::HashMap<usize, f64>>>,
+}
+
+#[cfg(any(test, not(any(windows, target_os = "linux"))))]
+impl MockSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Test-only: seeds the value `value()` will later hand back for `core`.
+    #[cfg(test)]
+    pub fn set_value(&self, core: usize, value: f64) {
+        self.values.lock().unwrap().insert(core, value);
+    }
+}
+
+#[cfg(any(test, not(any(windows, target_os = "linux"))))]
+impl CounterSource for MockSource {
+    fn add_core(&mut self, _core_index: usize) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn collect(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn value(&self, core: usize) -> Option<f64> {
+        self.values.lock().unwrap().get(&core).copied()
+    }
+}
+
+/// Tracks the idle and total jiffies `ProcStatCounterSource` last saw for a
+/// core, so the next `collect()` can diff against it.
+#[cfg(target_os = "linux")]
+#[derive(Clone, Copy)]
+struct CpuTimes {
+    idle: u64,
+    total: u64,
+}
+
+/// Reads per-core utilization from `/proc/stat`, the same source `top` and
+/// `mpstat` use. Each `collect()` diffs the jiffie counters against the
+/// previous sample, so `value()` returns `None` for a core until its second
+/// collection.
+#[cfg(target_os = "linux")]
+pub struct ProcStatCounterSource {
+    stat_path: std::path::PathBuf,
+    cores: Vec<usize>,
+    previous: std::collections::HashMap<usize, CpuTimes>,
+    utilization: std::collections::HashMap<usize, f64>,
+}
+
+#[cfg(target_os = "linux")]
+impl ProcStatCounterSource {
+    pub fn new() -> Self {
+        Self::from_stat_path(std::path::PathBuf::from("/proc/stat"))
+    }
+
+    /// The guts of `new`, parameterized over the `/proc/stat` path so tests
+    /// can point it at a fake file instead of the real one.
+    fn from_stat_path(stat_path: std::path::PathBuf) -> Self {
+        ProcStatCounterSource {
+            stat_path,
+            cores: Vec::new(),
+            previous: std::collections::HashMap::new(),
+            utilization: std::collections::HashMap::new(),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Default for ProcStatCounterSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl CounterSource for ProcStatCounterSource {
+    fn add_core(&mut self, core_index: usize) -> Result<(), String> {
+        self.cores.push(core_index);
+        Ok(())
+    }
+
+    fn collect(&mut self) -> Result<(), String> {
+        let contents = std::fs::read_to_string(&self.stat_path)
+            .map_err(|e| format!("failed to read {}: {}", self.stat_path.display(), e))?;
+        for &core in &self.cores {
+            let Some(times) = parse_proc_stat_cpu_line(&contents, core) else {
+                continue;
+            };
+            if let Some(previous) = self.previous.insert(core, times) {
+                let idle_delta = times.idle.saturating_sub(previous.idle);
+                let total_delta = times.total.saturating_sub(previous.total);
+                if total_delta > 0 {
+                    let busy_fraction = 1.0 - idle_delta as f64 / total_delta as f64;
+                    self.utilization.insert(core, 100.0 * busy_fraction);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn value(&self, core: usize) -> Option<f64> {
+        self.utilization.get(&core).copied()
+    }
+}
+
+/// Parses the `cpu<core>` line of a `/proc/stat` dump into idle and total
+/// jiffie counts. Idle time is `idle + iowait`, matching how `mpstat`
+/// accounts for time the core spent waiting on I/O as non-busy. Returns
+/// `None` if the core has no line or the line is shorter than expected.
+#[cfg(target_os = "linux")]
+fn parse_proc_stat_cpu_line(contents: &str, core: usize) -> Option<CpuTimes> {
+    let label = format!("cpu{}", core);
+    let line = contents
+        .lines()
+        .find(|line| line.split_whitespace().next() == Some(label.as_str()))?;
+    let fields: Vec<u64> = line
+        .split_whitespace()
+        .skip(1)
+        .filter_map(|field| field.parse::<u64>().ok())
+        .collect();
+    // user, nice, system, idle, iowait, irq, softirq, steal, guest, guest_nice
+    let idle_time = *fields.get(3)? + fields.get(4).copied().unwrap_or(0);
+    let total_time = fields.iter().sum();
+    Some(CpuTimes {
+        idle: idle_time,
+        total: total_time,
+    })
+}
+
+/// The `CounterSource` a `CpuMonitor` uses unless told otherwise: PDH on
+/// Windows, `/proc/stat` deltas on Linux, an empty `MockSource` on every
+/// other platform.
+#[cfg(windows)]
+pub fn default_counter_source() -> Box<dyn CounterSource> {
+    Box::new(PdhCounterSource::new())
+}
+
+#[cfg(target_os = "linux")]
+pub fn default_counter_source() -> Box<dyn CounterSource> {
+    Box::new(ProcStatCounterSource::new())
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+pub fn default_counter_source() -> Box<dyn CounterSource> {
+    Box::new(MockSource::new())
 }
 
 pub struct CpuMonitor {
@@ -27,159 +301,123 @@ pub struct CpuMonitor {
     event_type: CpuEvent,
     active: Arc<(Mutex<bool>, Condvar)>,
     worker: Mutex<Option<JoinHandle<()>>>,
+    stop: Arc<AtomicBool>,
+    sample_window: Duration,
+    source: Mutex<Box<dyn CounterSource>>,
 }
 
 impl CpuMonitor {
-    pub fn new(cores_to_monitor: Vec<usize>, event_type: CpuEvent, active: bool) -> Self {
+    pub fn new(
+        cores_to_monitor: Vec<usize>,
+        event_type: CpuEvent,
+        active: bool,
+        sample_window: Duration,
+    ) -> Self {
+        Self::with_source(
+            cores_to_monitor,
+            event_type,
+            active,
+            sample_window,
+            default_counter_source(),
+        )
+    }
+
+    /// Like `new`, but lets callers (tests, simulations) supply their own `CounterSource`.
+    pub fn with_source(
+        cores_to_monitor: Vec<usize>,
+        event_type: CpuEvent,
+        active: bool,
+        sample_window: Duration,
+        source: Box<dyn CounterSource>,
+    ) -> Self {
         CpuMonitor {
             cores_to_monitor,
             event_type,
             active: Arc::new((Mutex::new(active), Condvar::new())),
             worker: None.into(),
+            stop: Arc::new(AtomicBool::new(false)),
+            sample_window,
+            source: Mutex::new(source),
         }
     }
 
-    #[cfg(windows)]
     pub fn start(self: Arc<Self>, sender: mpsc::Sender<CpuEvent>) {
-        let self_clone = self.clone();
         let thread_name = self.get_thread_name();
-        let worker = thread::Builder::new().name(thread_name).spawn(move || {
-            // Using PDH to monitor CPU usage
-            unsafe {
-                // Open a query
-                let mut query: isize = 0;
-                let mut status = PdhOpenQueryW(PCWSTR::null(), 0, &mut query);
-                if status != ERROR_SUCCESS.0 {
-                    panic!("PdhOpenQueryW failed with error: {}", status);
-                }
+        let monitor = self.clone();
+        let worker = thread::Builder::new()
+            .name(thread_name)
+            .spawn(move || monitor.run(sender))
+            .unwrap();
+        *self.worker.lock().unwrap() = Some(worker);
+    }
 
-                // Buffer to hold the counter paths for each core
-                let mut counter_handles: Vec<isize> = Vec::new();
-
-                // Create a counter for each core
-                for &core_index in &self.cores_to_monitor {
-                    let counter_path = format!(
-                        r"\Processor Information(0,{})\% Processor Utility",
-                        core_index
-                    );
-                    let mut counter_handle: isize = 0;
-                    let wide_counter_path =
-                        widestring::U16CString::from_str(&counter_path).unwrap();
-                    status = PdhAddCounterW(
-                        query,
-                        PCWSTR(wide_counter_path.as_ptr()),
-                        0,
-                        &mut counter_handle,
-                    );
-                    if status != ERROR_SUCCESS.0 {
-                        panic!("PdhAddCounterW failed with error: {}", status);
-                    }
-                    counter_handles.push(counter_handle);
+    fn run(&self, sender: mpsc::Sender<CpuEvent>) {
+        {
+            let mut source = self.source.lock().unwrap();
+            if let Err(e) = source.open() {
+                let _ = sender.send(CpuEvent::MonitorError(e));
+                return;
+            }
+            for &core_index in &self.cores_to_monitor {
+                if let Err(e) = source.add_core(core_index) {
+                    let _ = sender.send(CpuEvent::MonitorError(e));
+                    return;
                 }
+            }
+        }
 
-                loop {
-                    self.wait_for_active();
+        while !self.stop.load(Ordering::SeqCst) {
+            self.wait_for_active();
+            if self.stop.load(Ordering::SeqCst) {
+                break;
+            }
 
-                    // Collect the query data
-                    status = PdhCollectQueryData(query);
-                    if status != ERROR_SUCCESS.0 {
-                        panic!("PdhCollectQueryData failed with error: {:x}", status);
-                    }
+            if let Err(e) = self.source.lock().unwrap().collect() {
+                let _ = sender.send(CpuEvent::MonitorError(e));
+                thread::sleep(self.sample_window);
+                continue;
+            }
 
-                    // Wait for a second to have a time sample
-                    std::thread::sleep(std::time::Duration::from_secs(1));
+            thread::sleep(self.sample_window);
 
-                    // Collect the second set of data
-                    status = PdhCollectQueryData(query);
-                    if status != ERROR_SUCCESS.0 {
-                        panic!("PdhCollectQueryData failed with error: {:x}", status);
-                    }
-                    // Retrieve and process the calculated counter value for each core
-                    let mut fully_consumed_cores = Vec::new();
-                    let mut counter_handles_index = 0;
-                    for &core_index in &self.cores_to_monitor {
-                        let mut counter_value: PDH_FMT_COUNTERVALUE =
-                            PDH_FMT_COUNTERVALUE::default();
-                        status = PdhGetFormattedCounterValue(
-                            counter_handles[counter_handles_index],
-                            PDH_FMT_DOUBLE,
-                            Some(std::ptr::null_mut()),
-                            &mut counter_value,
-                        );
-                        counter_handles_index += 1;
-                        // This cpu has been shut down.
-                        if status == PDH_CALC_NEGATIVE_VALUE
-                            || status == PDH_CALC_NEGATIVE_DENOMINATOR
-                        {
-                            continue;
-                        }
-
-                        if status == PDH_INVALID_ARGUMENT {
-                            eprintln!(
-                                "Invalid argument for counter index {}, core id: {}.",
-                                counter_handles_index - 1,
-                                core_index
-                            );
-                            continue;
-                        }
-
-                        if status == PDH_INVALID_DATA {
-                            eprintln!(
-                                "Invalid data for counter index {}, core id: {}.",
-                                counter_handles_index - 1,
-                                core_index
-                            );
-                            continue;
-                        }
-
-                        if status != ERROR_SUCCESS.0 {
-                            panic!(
-                                "PdhGetFormattedCounterValue failed with error: {:x}",
-                                status
-                            );
-                        }
-                        if counter_value.CStatus == PDH_CSTATUS_VALID_DATA
-                            || counter_value.CStatus == PDH_CSTATUS_NEW_DATA
-                        {
-                            let value = counter_value.Anonymous.doubleValue;
-                            if value >= 100.0 {
-                                fully_consumed_cores.push(core_index);
-                            }
-                        }
+            let mut source = self.source.lock().unwrap();
+            if let Err(e) = source.collect() {
+                let _ = sender.send(CpuEvent::MonitorError(e));
+                continue;
+            }
+
+            let mut fully_consumed_cores = Vec::new();
+            for &core_index in &self.cores_to_monitor {
+                if let Some(value) = source.value(core_index) {
+                    if value >= 100.0 {
+                        fully_consumed_cores.push(core_index);
                     }
+                }
+            }
+            drop(source);
 
-                    // Send event if there are fully consumed cores
-                    if !fully_consumed_cores.is_empty() {
-                        let event = match &self.event_type {
-                            CpuEvent::EfficiencyCoreMonitor(_) => {
-                                CpuEvent::EfficiencyCoreMonitor(fully_consumed_cores)
-                            }
-                            CpuEvent::PerformanceCoreMonitor(_) => {
-                                CpuEvent::PerformanceCoreMonitor(fully_consumed_cores)
-                            }
-                        };
-                        if let Err(e) = sender.send(event) {
-                            // Handle error (e.g., the receiver might have been dropped)
-                            // For simplicity, we panic here, but you may want to handle it more gracefully
-                            panic!("Failed to send CpuEvent: {}", e);
-                        }
+            if !fully_consumed_cores.is_empty() {
+                let event = match &self.event_type {
+                    CpuEvent::EfficiencyCoreMonitor(_) => {
+                        CpuEvent::EfficiencyCoreMonitor(fully_consumed_cores)
+                    }
+                    CpuEvent::PerformanceCoreMonitor(_) => {
+                        CpuEvent::PerformanceCoreMonitor(fully_consumed_cores)
                     }
+                    CpuEvent::MonitorError(_) => unreachable!("event_type is never MonitorError"),
+                };
+                if sender.send(event).is_err() {
+                    // Receiver dropped; nothing left to report to, so exit quietly.
+                    break;
                 }
-                // status = PdhCloseQuery(query);
-                // if status != ERROR_SUCCESS.0 {
-                //     panic!("PdhCloseQuery failed with error: {}", status);
-                // }
             }
-        });
-        let mut worker_guard = self_clone.worker.lock().unwrap();
-        *worker_guard = Some(worker.unwrap());
+        }
     }
 
     fn wait_for_active(&self) {
         let (lock, cvar) = &*self.active;
         let mut active = lock.lock().unwrap();
-        while !*active {
-            // Wait for the condition variable to be notified
+        while !*active && !self.stop.load(Ordering::SeqCst) {
             active = cvar.wait(active).unwrap();
         }
     }
@@ -188,6 +426,7 @@ impl CpuMonitor {
         match self.event_type {
             CpuEvent::EfficiencyCoreMonitor(_) => "EfficiencyCoreMonitor_thread".to_string(),
             CpuEvent::PerformanceCoreMonitor(_) => "PerformanceCoreMonitor_thread".to_string(),
+            CpuEvent::MonitorError(_) => unreachable!("event_type is never MonitorError"),
         }
     }
 
@@ -205,11 +444,31 @@ impl CpuMonitor {
         cvar.notify_one();
     }
 
+    /// Test-only: lets assertions observe the effect of `pause`/`resume`
+    /// without reaching into the monitor's internals.
+    #[cfg(test)]
     pub fn is_active(&self) -> bool {
         let (lock, _cvar) = &*self.active;
         let active = lock.lock().unwrap();
         *active
     }
+
+    /// Signals the worker thread to exit the sampling loop and joins it.
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::SeqCst);
+        let (lock, cvar) = &*self.active;
+        *lock.lock().unwrap() = true;
+        cvar.notify_one();
+        if let Some(handle) = self.worker.lock().unwrap().take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for CpuMonitor {
+    fn drop(&mut self) {
+        self.stop();
+    }
 }
 
 pub struct SpinLooper {
@@ -281,3 +540,129 @@ fn set_lowest_priority() {
         let _ = SetThreadPriority(current_thread, THREAD_PRIORITY_IDLE);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mock_source_drives_a_fully_consumed_event_and_stops_cleanly() {
+        let mock = MockSource::new();
+        mock.set_value(0, 100.0);
+
+        let monitor = Arc::new(CpuMonitor::with_source(
+            vec![0],
+            CpuEvent::EfficiencyCoreMonitor(Vec::new()),
+            true,
+            Duration::from_millis(1),
+            Box::new(mock),
+        ));
+
+        let (sender, receiver) = mpsc::channel();
+        monitor.clone().start(sender);
+
+        let event = receiver
+            .recv_timeout(Duration::from_secs(1))
+            .expect("monitor should report the fully-consumed core");
+        match event {
+            CpuEvent::EfficiencyCoreMonitor(cores) => assert_eq!(cores, vec![0]),
+            _ => panic!("expected an EfficiencyCoreMonitor event"),
+        }
+
+        monitor.stop();
+        assert!(monitor.worker.lock().unwrap().is_none());
+    }
+
+    /// A `CounterSource` whose `open()` always fails, used to exercise the
+    /// `MonitorError` propagation path instead of a panic.
+    struct FailingSource;
+
+    impl CounterSource for FailingSource {
+        fn open(&mut self) -> Result<(), String> {
+            Err("boom".to_string())
+        }
+
+        fn add_core(&mut self, _core_index: usize) -> Result<(), String> {
+            Ok(())
+        }
+
+        fn collect(&mut self) -> Result<(), String> {
+            Ok(())
+        }
+
+        fn value(&self, _core: usize) -> Option<f64> {
+            None
+        }
+    }
+
+    #[test]
+    fn open_failure_is_reported_as_monitor_error_instead_of_panicking() {
+        let monitor = Arc::new(CpuMonitor::with_source(
+            vec![0],
+            CpuEvent::PerformanceCoreMonitor(Vec::new()),
+            true,
+            Duration::from_millis(1),
+            Box::new(FailingSource),
+        ));
+
+        let (sender, receiver) = mpsc::channel();
+        monitor.clone().start(sender);
+
+        let event = receiver
+            .recv_timeout(Duration::from_secs(1))
+            .expect("monitor should report the open() failure");
+        assert!(matches!(event, CpuEvent::MonitorError(ref msg) if msg == "boom"));
+
+        monitor.stop();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn proc_stat_counter_reports_utilization_delta_between_collects() {
+        let path = std::env::temp_dir().join(format!(
+            "cpu_event_test_proc_stat_{}",
+            std::process::id()
+        ));
+
+        std::fs::write(
+            &path,
+            "cpu  100 0 100 800 0 0 0 0 0 0\ncpu0 100 0 100 800 0 0 0 0 0 0\n",
+        )
+        .unwrap();
+        let mut source = ProcStatCounterSource::from_stat_path(path.clone());
+        source.add_core(0).unwrap();
+
+        // First collect only seeds the previous sample; nothing to diff against yet.
+        source.collect().unwrap();
+        assert_eq!(source.value(0), None);
+
+        // 50 more busy jiffies and 50 more idle jiffies over the same window:
+        // 50% utilization.
+        std::fs::write(
+            &path,
+            "cpu  150 0 100 850 0 0 0 0 0 0\ncpu0 150 0 100 850 0 0 0 0 0 0\n",
+        )
+        .unwrap();
+        source.collect().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(source.value(0), Some(50.0));
+    }
+
+    #[test]
+    fn pause_and_resume_toggle_is_active() {
+        let monitor = Arc::new(CpuMonitor::with_source(
+            vec![0],
+            CpuEvent::EfficiencyCoreMonitor(Vec::new()),
+            true,
+            Duration::from_secs(1),
+            Box::new(MockSource::new()),
+        ));
+
+        assert!(monitor.is_active());
+        monitor.pause();
+        assert!(!monitor.is_active());
+        monitor.resume();
+        assert!(monitor.is_active());
+    }
+}
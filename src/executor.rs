@@ -0,0 +1,322 @@
+use core_affinity::CoreId;
+use crossbeam_deque::{Injector, Steal, Stealer, Worker};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// A unit of work submitted to a [`CoreExecutor`].
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// Which class of core a task (or worker) belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoreClass {
+    Performance,
+    Efficiency,
+}
+
+struct Task {
+    class: CoreClass,
+    job: Job,
+}
+
+/// The shared injector, split per [`CoreClass`] so a worker draining new
+/// submissions can prefer its own class's work over crossing classes, the
+/// same preference order used when stealing from peers' local queues.
+struct Injectors {
+    performance: Injector<Task>,
+    efficiency: Injector<Task>,
+}
+
+impl Injectors {
+    fn new() -> Self {
+        Injectors {
+            performance: Injector::new(),
+            efficiency: Injector::new(),
+        }
+    }
+
+    fn push(&self, task: Task) {
+        self.for_class(task.class).push(task);
+    }
+
+    fn for_class(&self, class: CoreClass) -> &Injector<Task> {
+        match class {
+            CoreClass::Performance => &self.performance,
+            CoreClass::Efficiency => &self.efficiency,
+        }
+    }
+}
+
+/// A peer worker's steal handle, tagged with the class of core it runs on so
+/// stealers can prefer same-class work before crossing classes.
+struct Peer {
+    class: CoreClass,
+    stealer: Stealer<Task>,
+}
+
+/// Blocks idle workers on a `Condvar` instead of spinning, waking them up
+/// whenever new work is submitted.
+struct Parker {
+    lock: Mutex<()>,
+    cvar: Condvar,
+}
+
+impl Parker {
+    fn new() -> Self {
+        Parker {
+            lock: Mutex::new(()),
+            cvar: Condvar::new(),
+        }
+    }
+
+    fn wake_all(&self) {
+        self.cvar.notify_all();
+    }
+
+    fn park(&self, timeout: Duration) {
+        let guard = self.lock.lock().unwrap();
+        let _ = self.cvar.wait_timeout(guard, timeout).unwrap();
+    }
+}
+
+/// A work-stealing executor that pins one worker thread per core and
+/// schedules tasks according to a P-core/E-core preference hint, reusing the
+/// affinity machinery that [`crate::cpu_event::SpinLooper`] pins threads with.
+pub struct CoreExecutor {
+    injectors: Arc<Injectors>,
+    parker: Arc<Parker>,
+    shutdown: Arc<AtomicBool>,
+    /// Mutex-wrapped so `shutdown` can drain and join through `&self`,
+    /// mirroring how `crate::cpu_event::CpuMonitor` joins its worker through
+    /// a `Mutex<Option<JoinHandle<()>>>` from its own `Drop` impl.
+    handles: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl CoreExecutor {
+    /// Spawns one worker per id in `performance_cores` and `efficiency_cores`,
+    /// pinning each to its core and tagging it with the matching `CoreClass`.
+    pub fn new(performance_cores: Vec<usize>, efficiency_cores: Vec<usize>) -> Self {
+        let injectors = Arc::new(Injectors::new());
+        let parker = Arc::new(Parker::new());
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let workers: Vec<(usize, CoreClass, Worker<Task>)> = performance_cores
+            .into_iter()
+            .map(|id| (id, CoreClass::Performance, Worker::new_fifo()))
+            .chain(
+                efficiency_cores
+                    .into_iter()
+                    .map(|id| (id, CoreClass::Efficiency, Worker::new_fifo())),
+            )
+            .collect();
+
+        // Build every peer's Stealer up front so the pool is fully registered
+        // before any worker thread starts and can observe it.
+        let peers: Arc<Vec<Peer>> = Arc::new(
+            workers
+                .iter()
+                .map(|(_, class, worker)| Peer {
+                    class: *class,
+                    stealer: worker.stealer(),
+                })
+                .collect(),
+        );
+
+        let handles = workers
+            .into_iter()
+            .map(|(core_id, class, local)| {
+                let injectors = injectors.clone();
+                let peers = peers.clone();
+                let shutdown = shutdown.clone();
+                let parker = parker.clone();
+                thread::spawn(move || {
+                    core_affinity::set_for_current(CoreId { id: core_id });
+                    run_worker(class, local, &injectors, &peers, &shutdown, &parker);
+                })
+            })
+            .collect();
+
+        CoreExecutor {
+            injectors,
+            parker,
+            shutdown,
+            handles: Mutex::new(handles),
+        }
+    }
+
+    /// Submits `job`, preferring to run it on a worker whose `CoreClass` matches `class`.
+    pub fn spawn<F>(&self, class: CoreClass, job: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.injectors.push(Task {
+            class,
+            job: Box::new(job),
+        });
+        self.parker.wake_all();
+    }
+
+    /// Signals every worker to stop and joins its thread. Safe to call more
+    /// than once; later calls just find nothing left to join.
+    pub fn shutdown(&self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        self.parker.wake_all();
+        for handle in self.handles.lock().unwrap().drain(..) {
+            handle.join().expect("Failed to join executor worker thread");
+        }
+    }
+}
+
+impl Drop for CoreExecutor {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+fn run_worker(
+    class: CoreClass,
+    local: Worker<Task>,
+    injectors: &Injectors,
+    peers: &[Peer],
+    shutdown: &AtomicBool,
+    parker: &Parker,
+) {
+    while !shutdown.load(Ordering::SeqCst) {
+        match find_task(class, &local, injectors, peers) {
+            Some(task) => (task.job)(),
+            // Local queue, injectors, and every peer are empty: park until a new
+            // submission wakes us, rather than busy-spinning.
+            None => parker.park(Duration::from_millis(50)),
+        }
+    }
+}
+
+/// Drains a single injector, retrying on contention and returning `None` once
+/// it's observed empty.
+fn steal_from_injector(injector: &Injector<Task>, local: &Worker<Task>) -> Option<Task> {
+    loop {
+        match injector.steal_batch_and_pop(local) {
+            Steal::Success(task) => return Some(task),
+            Steal::Retry => continue,
+            Steal::Empty => return None,
+        }
+    }
+}
+
+fn find_task(
+    class: CoreClass,
+    local: &Worker<Task>,
+    injectors: &Injectors,
+    peers: &[Peer],
+) -> Option<Task> {
+    if let Some(task) = local.pop() {
+        return Some(task);
+    }
+
+    // Prefer this worker's own class injector before crossing into the other
+    // class's queue, mirroring the same-class-first rule used for peers below.
+    let other_class = match class {
+        CoreClass::Performance => CoreClass::Efficiency,
+        CoreClass::Efficiency => CoreClass::Performance,
+    };
+    if let Some(task) = steal_from_injector(injectors.for_class(class), local) {
+        return Some(task);
+    }
+    if let Some(task) = steal_from_injector(injectors.for_class(other_class), local) {
+        return Some(task);
+    }
+
+    // Prefer peers in the same core class before crossing into the other class.
+    let (same_class, other_class): (Vec<&Peer>, Vec<&Peer>) =
+        peers.iter().partition(|peer| peer.class == class);
+    for peer in same_class.into_iter().chain(other_class) {
+        loop {
+            match peer.stealer.steal() {
+                Steal::Success(task) => return Some(task),
+                Steal::Retry => continue,
+                Steal::Empty => break,
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_op_task(class: CoreClass) -> Task {
+        Task {
+            class,
+            job: Box::new(|| {}),
+        }
+    }
+
+    #[test]
+    fn injector_drains_matching_class_before_crossing() {
+        let injectors = Injectors::new();
+        let local: Worker<Task> = Worker::new_fifo();
+
+        // Pushed in this order so a class-blind drain would hand the
+        // Efficiency task to a Performance-class worker first.
+        injectors.push(no_op_task(CoreClass::Efficiency));
+        injectors.push(no_op_task(CoreClass::Performance));
+
+        let first = find_task(CoreClass::Performance, &local, &injectors, &[])
+            .expect("performance task should be available");
+        assert_eq!(first.class, CoreClass::Performance);
+
+        let second = find_task(CoreClass::Performance, &local, &injectors, &[])
+            .expect("efficiency task should still be available after crossing classes");
+        assert_eq!(second.class, CoreClass::Efficiency);
+    }
+
+    #[test]
+    fn executor_runs_spawned_jobs_then_shuts_down_cleanly() {
+        use std::sync::mpsc;
+
+        let executor = CoreExecutor::new(vec![0], vec![]);
+        let (tx, rx) = mpsc::channel();
+
+        executor.spawn(CoreClass::Performance, move || {
+            tx.send(()).unwrap();
+        });
+
+        rx.recv_timeout(Duration::from_secs(1))
+            .expect("spawned job should run on a worker thread");
+
+        // Joins every worker thread; hangs if shutdown fails to wake a parked worker.
+        executor.shutdown();
+    }
+
+    #[test]
+    fn worker_steals_same_class_peer_before_other_class_peer() {
+        let injectors = Injectors::new();
+        let local: Worker<Task> = Worker::new_fifo();
+
+        let perf_peer_local: Worker<Task> = Worker::new_fifo();
+        perf_peer_local.push(no_op_task(CoreClass::Performance));
+        let eff_peer_local: Worker<Task> = Worker::new_fifo();
+        eff_peer_local.push(no_op_task(CoreClass::Efficiency));
+
+        let peers = vec![
+            Peer {
+                class: CoreClass::Performance,
+                stealer: perf_peer_local.stealer(),
+            },
+            Peer {
+                class: CoreClass::Efficiency,
+                stealer: eff_peer_local.stealer(),
+            },
+        ];
+
+        // An Efficiency-class worker with nothing in its own local queue or
+        // either injector should steal from its Efficiency peer, not the
+        // Performance peer, even though the latter is listed first here.
+        let task = find_task(CoreClass::Efficiency, &local, &injectors, &peers)
+            .expect("efficiency peer's task should be stolen");
+        assert_eq!(task.class, CoreClass::Efficiency);
+    }
+}
@@ -1,50 +1,430 @@
+mod control;
+mod core_detection;
+mod cpu_event;
+mod executor;
+/// Test-only: a seeded event-injection harness for exercising
+/// `control::CoreStateController`'s state transitions deterministically.
+/// Nothing outside `#[cfg(test)]` code ever drives it.
+#[cfg(test)]
+mod simulation;
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 use tokio::io::{self, AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Notify;
+
+/// How often a backend's health check re-probes it.
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// How long a health check waits for the backend to accept a connection.
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A single listener and the backends it round-robins connections across.
+struct RouteConfig {
+    listen_addr: String,
+    backend_addrs: Vec<String>,
+    /// Connections with no traffic in either direction for this long are shut down.
+    idle_timeout: Duration,
+    /// If set, a new connection preempts whichever session already holds its backend.
+    exclusive: bool,
+}
+
+/// A backend the proxy forwards to, with a liveness flag a background task
+/// keeps fresh and, for exclusive routes, the session currently holding it.
+struct Backend {
+    addr: String,
+    healthy: AtomicBool,
+    current_session: Mutex<Option<Arc<Session>>>,
+}
+
+/// A handle on an in-flight connection, used to preempt it when a new client
+/// takes over an exclusive backend.
+struct Session {
+    /// Notified to ask the session to shut down.
+    terminate: Notify,
+    /// Notified once the session has actually finished.
+    ended: Notify,
+}
+
+/// Tracks the last time either direction of a proxied connection saw traffic,
+/// so both copy loops can agree on whether the connection is truly idle.
+struct IdleGuard {
+    last_activity: Mutex<Instant>,
+}
+
+impl IdleGuard {
+    fn new() -> Self {
+        IdleGuard {
+            last_activity: Mutex::new(Instant::now()),
+        }
+    }
+
+    fn touch(&self) {
+        *self.last_activity.lock().unwrap() = Instant::now();
+    }
+
+    fn idle_for(&self) -> Duration {
+        self.last_activity.lock().unwrap().elapsed()
+    }
+}
+
+/// Starts the background CPU-class governor: detects the E-core/P-core
+/// split, pins a `CoreExecutor` across it, and lets a `CoreStateController`
+/// drive that executor's rebalancing as load shifts between core classes.
+/// Runs on its own thread, independent of the proxy's tokio tasks, since
+/// `CoreStateController::run` blocks.
+fn start_core_governor() {
+    let e_core_ids = core_detection::identify_e_cores().unwrap_or_default();
+    let all_core_ids: Vec<usize> = core_affinity::get_core_ids()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|core| core.id)
+        .collect();
+    let p_core_ids: Vec<usize> = all_core_ids
+        .into_iter()
+        .filter(|id| !e_core_ids.contains(id))
+        .collect();
+
+    if e_core_ids.is_empty() || p_core_ids.is_empty() {
+        // Homogeneous or undetectable topology: nothing to govern.
+        return;
+    }
+
+    thread::spawn(move || {
+        let mut controller = control::CoreStateController::new(e_core_ids, p_core_ids);
+        if let Err(e) = controller.run() {
+            eprintln!("core state controller stopped: {}", e);
+        }
+    });
+}
 
 #[tokio::main]
 async fn main() {
-    let listen_port = "5556";
-    let forward_address = "127.0.0.1:5555";
+    start_core_governor();
+
+    let routes = vec![RouteConfig {
+        listen_addr: "0.0.0.0:5556".to_string(),
+        backend_addrs: vec!["127.0.0.1:5555".to_string()],
+        idle_timeout: Duration::from_secs(300),
+        exclusive: false,
+    }];
+
+    let route_tasks: Vec<_> = routes.into_iter().map(|route| tokio::spawn(run_route(route))).collect();
+
+    for task in route_tasks {
+        let _ = task.await;
+    }
+}
+
+/// Binds `route.listen_addr`, starts a health-check task per backend, and
+/// accepts connections forever, handing each off to `proxy_connection`.
+async fn run_route(route: RouteConfig) {
+    let backends: Vec<Arc<Backend>> = route
+        .backend_addrs
+        .into_iter()
+        .map(|addr| {
+            Arc::new(Backend {
+                addr,
+                healthy: AtomicBool::new(true),
+                current_session: Mutex::new(None),
+            })
+        })
+        .collect();
+
+    for backend in &backends {
+        tokio::spawn(health_check_loop(backend.clone()));
+    }
 
-    // Start listening for incoming connections on listen_port.
-    let listener = match TcpListener::bind(format!("0.0.0.0:{}", listen_port)).await {
+    let listener = match TcpListener::bind(&route.listen_addr).await {
         Ok(listener) => listener,
         Err(e) => {
-            eprintln!("Error starting TCP listener on port {}: {}", listen_port, e);
+            eprintln!(
+                "Error starting TCP listener on {}: {}",
+                route.listen_addr, e
+            );
             return;
         }
     };
 
     println!(
-        "Listening on port {}, forwarding to {}",
-        listen_port, forward_address
+        "Listening on {}, forwarding to {:?}",
+        route.listen_addr,
+        backends.iter().map(|b| b.addr.as_str()).collect::<Vec<_>>()
     );
 
+    let next_backend = AtomicUsize::new(0);
+
     loop {
-        // Accept new connections.
-        let (mut client_conn, client_addr) =
-            listener.accept().await.expect("Error accepting connection");
+        let (client_conn, client_addr) = match listener.accept().await {
+            Ok(pair) => pair,
+            Err(e) => {
+                eprintln!("Error accepting connection on {}: {}", route.listen_addr, e);
+                continue;
+            }
+        };
         println!("Accepted connection from {}", client_addr);
 
-        let forward_address = forward_address.to_string();
+        let Some(backend) = pick_backend(&backends, &next_backend) else {
+            eprintln!(
+                "No healthy backend available for {}, dropping connection from {}",
+                route.listen_addr, client_addr
+            );
+            continue;
+        };
+
+        let idle_timeout = route.idle_timeout;
+        let exclusive = route.exclusive;
+
+        // The takeover handshake (and the wait for any preempted session to
+        // finish) happens inside the spawned task, not here, so one slow
+        // takeover on this backend can't stall the accept loop for
+        // connections destined to the route's other backends.
+        tokio::spawn(async move {
+            let session = if exclusive {
+                Some(takeover_session(&backend).await)
+            } else {
+                None
+            };
+            let result =
+                proxy_connection(client_conn, &backend.addr, idle_timeout, session.clone()).await;
+            if let Err(e) = result {
+                eprintln!("Connection from {} failed: {}", client_addr, e);
+            }
+            if let Some(session) = &session {
+                release_session(&backend, session);
+            }
+        });
+    }
+}
+
+/// Signals and waits for any session currently holding `backend` to finish,
+/// then registers and returns a new session in its place.
+async fn takeover_session(backend: &Backend) -> Arc<Session> {
+    let previous = backend.current_session.lock().unwrap().take();
+    if let Some(previous) = previous {
+        previous.terminate.notify_one();
+        previous.ended.notified().await;
+    }
+
+    let session = Arc::new(Session {
+        terminate: Notify::new(),
+        ended: Notify::new(),
+    });
+    *backend.current_session.lock().unwrap() = Some(session.clone());
+    session
+}
+
+/// Clears `backend`'s current session if it's still `session` (a later
+/// takeover may have already replaced it) and wakes anyone waiting on it.
+fn release_session(backend: &Backend, session: &Arc<Session>) {
+    let mut current = backend.current_session.lock().unwrap();
+    if matches!(current.as_ref(), Some(active) if Arc::ptr_eq(active, session)) {
+        *current = None;
+    }
+    drop(current);
+    session.ended.notify_one();
+}
+
+/// Picks the next backend in round-robin order, skipping any marked unhealthy.
+/// Returns `None` only if every backend is currently unhealthy.
+fn pick_backend(backends: &[Arc<Backend>], next: &AtomicUsize) -> Option<Arc<Backend>> {
+    let len = backends.len();
+    if len == 0 {
+        return None;
+    }
+    let start = next.fetch_add(1, Ordering::Relaxed);
+    (0..len)
+        .map(|offset| &backends[(start + offset) % len])
+        .find(|backend| backend.healthy.load(Ordering::Relaxed))
+        .cloned()
+}
+
+/// Periodically probes `backend` with a timed-out TCP connect and updates its
+/// health flag, so `pick_backend` can skip it while it's down.
+async fn health_check_loop(backend: Arc<Backend>) {
+    loop {
+        let healthy = tokio::time::timeout(HEALTH_CHECK_TIMEOUT, TcpStream::connect(&backend.addr))
+            .await
+            .map(|result| result.is_ok())
+            .unwrap_or(false);
+        backend.healthy.store(healthy, Ordering::Relaxed);
+        tokio::time::sleep(HEALTH_CHECK_INTERVAL).await;
+    }
+}
+
+/// Connects to `backend_addr` and copies bytes in both directions until
+/// both directions have reached end of stream, either direction errors, the
+/// connection sits idle past
+/// `idle_timeout`, or (for an exclusive backend) `session` is preempted by a
+/// newer connection.
+async fn proxy_connection(
+    mut client_conn: TcpStream,
+    backend_addr: &str,
+    idle_timeout: Duration,
+    session: Option<Arc<Session>>,
+) -> io::Result<()> {
+    let mut server_conn = TcpStream::connect(backend_addr).await?;
+
+    // Split the TCP streams into read and write halves.
+    let (mut client_read, mut client_write) = client_conn.split();
+    let (mut server_read, mut server_write) = server_conn.split();
+
+    let activity = Arc::new(IdleGuard::new());
+    let copy_both = async {
+        tokio::try_join!(
+            copy_with_idle_timeout(&mut client_read, &mut server_write, idle_timeout, &activity),
+            copy_with_idle_timeout(&mut server_read, &mut client_write, idle_timeout, &activity),
+        )
+    };
+
+    match session {
+        Some(session) => {
+            tokio::select! {
+                result = copy_both => { result?; }
+                _ = session.terminate.notified() => {
+                    // A newer connection is taking over this backend; shut down quietly.
+                }
+            }
+        }
+        None => {
+            copy_both.await?;
+        }
+    }
+    Ok(())
+}
 
-        // Handle the connection in an asynchronous task.
+/// Like `tokio::io::copy`, but treats the connection as idle-timed-out (and
+/// returns `Ok`) once `activity` has seen no traffic from either direction
+/// for `idle_timeout`, instead of copying forever.
+async fn copy_with_idle_timeout<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    idle_timeout: Duration,
+    activity: &IdleGuard,
+) -> io::Result<()>
+where
+    R: AsyncReadExt + Unpin,
+    W: AsyncWriteExt + Unpin,
+{
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = match tokio::time::timeout(idle_timeout, reader.read(&mut buf)).await {
+            Ok(result) => result?,
+            Err(_) => {
+                // This direction went quiet; only treat the connection as truly
+                // idle if the other direction has been quiet for just as long.
+                if activity.idle_for() >= idle_timeout {
+                    return Ok(());
+                }
+                continue;
+            }
+        };
+        if n == 0 {
+            return Ok(());
+        }
+        activity.touch();
+        writer.write_all(&buf[..n]).await?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn bind_local() -> (TcpListener, String) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        (listener, addr)
+    }
+
+    #[tokio::test]
+    async fn copy_closes_after_idle_timeout_with_no_traffic() {
+        let (listener, addr) = bind_local().await;
+        let accept = tokio::spawn(async move { listener.accept().await.unwrap().0 });
+        let mut client = TcpStream::connect(&addr).await.unwrap();
+        let mut server = accept.await.unwrap();
+
+        let (mut client_read, mut client_write) = client.split();
+        let (mut server_read, mut server_write) = server.split();
+        let activity = Arc::new(IdleGuard::new());
+        let idle_timeout = Duration::from_millis(30);
+
+        let copy_both = async {
+            tokio::try_join!(
+                copy_with_idle_timeout(&mut client_read, &mut server_write, idle_timeout, &activity),
+                copy_with_idle_timeout(&mut server_read, &mut client_write, idle_timeout, &activity),
+            )
+        };
+        let result = tokio::time::timeout(Duration::from_secs(5), copy_both)
+            .await
+            .expect("copy_with_idle_timeout should return once the connection has gone idle");
+
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn second_exclusive_takeover_preempts_first_session_and_closes_it() {
+        // A fake backend that just accepts connections and drains them,
+        // holding each one open until the proxy closes its end.
+        let (backend_listener, backend_addr) = bind_local().await;
         tokio::spawn(async move {
-            let mut server_conn = TcpStream::connect(&forward_address)
-                .await
-                .expect("Error connecting to forward address");
+            while let Ok((mut stream, _)) = backend_listener.accept().await {
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 1024];
+                    while let Ok(n) = stream.read(&mut buf).await {
+                        if n == 0 {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+
+        let backend = Arc::new(Backend {
+            addr: backend_addr,
+            healthy: AtomicBool::new(true),
+            current_session: Mutex::new(None),
+        });
 
-            // Split the TCP streams into read and write halves.
-            let (mut client_read, mut client_write) = client_conn.split();
-            let (mut server_read, mut server_write) = server_conn.split();
+        let (client_listener, client_addr) = bind_local().await;
 
-            // Copy data from the client to the server and vice-versa.
-            let client_to_server = io::copy(&mut client_read, &mut server_write);
-            let server_to_client = io::copy(&mut server_read, &mut client_write);
+        let _client1 = TcpStream::connect(&client_addr).await.unwrap();
+        let (server_side_client1, _) = client_listener.accept().await.unwrap();
 
-            // Use tokio::try_join to wait for both copy operations to complete.
-            let _ = tokio::try_join!(client_to_server, server_to_client)
-                .expect("Error while copying data between client and server");
+        let session1 = takeover_session(&backend).await;
+        let proxy1_backend = backend.clone();
+        let proxy1_session = session1.clone();
+        let proxy1 = tokio::spawn(async move {
+            let result = proxy_connection(
+                server_side_client1,
+                &proxy1_backend.addr,
+                Duration::from_secs(60),
+                Some(proxy1_session.clone()),
+            )
+            .await;
+            release_session(&proxy1_backend, &proxy1_session);
+            result
         });
+
+        // Give proxy1 a moment to connect to the backend and start copying.
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let _client2 = TcpStream::connect(&client_addr).await.unwrap();
+        let (server_side_client2, _) = client_listener.accept().await.unwrap();
+        let session2 = takeover_session(&backend).await;
+        assert!(!Arc::ptr_eq(&session1, &session2));
+
+        // session1's proxy must have observed the takeover's terminate notify
+        // and wound down, instead of lingering alongside session2.
+        let result1 = tokio::time::timeout(Duration::from_secs(5), proxy1)
+            .await
+            .expect("preempted session should terminate promptly")
+            .unwrap();
+        assert!(result1.is_ok());
+
+        release_session(&backend, &session2);
+        drop(server_side_client2);
     }
 }
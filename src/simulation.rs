@@ -0,0 +1,77 @@
+use crate::control::FakeClock;
+use crate::cpu_event::CpuEvent;
+use std::sync::mpsc::Sender;
+use std::time::Duration;
+
+/// A small seeded linear-congruential generator, so simulation runs are
+/// reproducible without pulling in an external RNG crate just for tests.
+struct Lcg(u64);
+
+impl Lcg {
+    fn new(seed: u64) -> Self {
+        Lcg(seed)
+    }
+
+    /// Returns a pseudo-random value in `[0, 1)`.
+    fn next_f64(&mut self) -> f64 {
+        // Constants from Knuth's MMIX generator.
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Knobs for a `SimulatedMonitor` run.
+pub struct SimulationConfig {
+    /// Probability in `[0, 1]` that an E-core-fully-consumed event fires on a given tick.
+    pub e_core_consumption_probability: f64,
+    /// Probability in `[0, 1]` that a P-core-fully-consumed event fires on a given tick.
+    pub p_core_consumption_probability: f64,
+    /// Number of ticks to simulate before stopping.
+    pub steps: usize,
+    /// RNG seed, so a run can be replayed exactly.
+    pub seed: u64,
+    /// How far the `FakeClock` is advanced per tick, so a run of quiet ticks
+    /// can actually exceed a `CoreStateController`'s idle timeout.
+    pub tick_interval: Duration,
+}
+
+/// Emits synthetic `CpuEvent`s instead of reading real hardware counters, so
+/// `CoreStateController`'s state transitions can be exercised deterministically.
+pub struct SimulatedMonitor;
+
+impl SimulatedMonitor {
+    /// Runs `config.steps` ticks, advancing `clock` by `config.tick_interval`
+    /// per tick instead of sleeping a real thread, and sending an event on
+    /// `sender` whenever that tick's RNG roll lands under the configured
+    /// probability. Calls `on_tick` once a tick's advance lands, before
+    /// rolling the next tick, so a `CoreStateController` can be driven in
+    /// lockstep with a `FakeClock` from a single thread — idle-timeout
+    /// convergence assertions then don't depend on real thread-scheduling
+    /// timing. Stops early if the receiving end is dropped.
+    pub fn run_synchronized(
+        config: SimulationConfig,
+        sender: Sender<CpuEvent>,
+        clock: &FakeClock,
+        mut on_tick: impl FnMut(),
+    ) {
+        let mut rng = Lcg::new(config.seed);
+        for _ in 0..config.steps {
+            if rng.next_f64() < config.e_core_consumption_probability
+                && sender
+                    .send(CpuEvent::EfficiencyCoreMonitor(vec![0]))
+                    .is_err()
+            {
+                return;
+            }
+            if rng.next_f64() < config.p_core_consumption_probability
+                && sender
+                    .send(CpuEvent::PerformanceCoreMonitor(vec![0]))
+                    .is_err()
+            {
+                return;
+            }
+            clock.advance(config.tick_interval);
+            on_tick();
+        }
+    }
+}